@@ -1,20 +1,22 @@
 #[cfg(not(windows))]
 compile_error!("this program only supports Windows");
 
-use std::fs::{DirEntry, OpenOptions, read_dir, rename};
+use std::fs::{OpenOptions, read_dir, rename};
 use std::path::{Path, PathBuf};
 use std::process::{Command, ExitCode};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, mpsc};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, SystemTime};
 
 use anyhow::Context as _;
 use chrono::{DateTime, Local, SecondsFormat};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use dirs_next::{data_local_dir, picture_dir};
 use lazy_regex::{Lazy, Regex, lazy_regex};
 use log::{debug, error, info};
 use notify::{RecursiveMode, Watcher};
+use notify_debouncer_full::new_debouncer;
+use serde::Deserialize;
 use tray_icon::menu::{Menu, MenuEvent, MenuItem};
 use tray_icon::{Icon, TrayIconBuilder};
 
@@ -36,6 +38,38 @@ struct Args {
     /// Dry run (print what would be renamed without actually renaming)
     #[arg(long)]
     dry_run: bool,
+
+    /// Config file path (defines the rename rules)
+    #[arg(long, default_value_os_t = default_config_file())]
+    config: PathBuf,
+
+    /// Keep only the N most recent screenshots, pruning the rest
+    #[arg(long)]
+    keep: Option<usize>,
+
+    /// What to do with pruned screenshots
+    #[arg(long, value_enum)]
+    retention_mode: Option<RetentionMode>,
+
+    /// Upload each renamed screenshot and copy the returned URL to the clipboard
+    #[arg(long)]
+    upload: bool,
+
+    /// Show desktop toast notifications on rename events (watch mode)
+    #[arg(long)]
+    notify: bool,
+}
+
+/// How `--keep` disposes of screenshots beyond the retained count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+#[value(rename_all = "lowercase")]
+enum RetentionMode {
+    /// Permanently delete pruned screenshots.
+    #[default]
+    Delete,
+    /// Move pruned screenshots into an `Archive/` subfolder.
+    Archive,
 }
 
 fn default_screenshots_dir() -> PathBuf {
@@ -52,11 +86,232 @@ fn default_log_file() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from(file_name))
 }
 
-static RE_SNIPPING_TOOL_JA: Lazy<Regex> =
-    lazy_regex!(r"^スクリーンショット (\d{4}-\d{2}-\d{2} \d{6})\.png$");
-static RE_OLD_SNIPPING_TOOL_JA: Lazy<Regex> =
-    lazy_regex!(r"^スクリーンショット_(\d{4})(\d{2})(\d{2})_(\d{6})\.png$");
-static RE_SCREENSHOT_JA: Lazy<Regex> = lazy_regex!(r"^スクリーンショット(?: \(\d+\))?\.png$");
+fn default_config_file() -> PathBuf {
+    let name = env!("CARGO_PKG_NAME");
+    let file_name = format!("{name}.toml");
+    data_local_dir()
+        .map(|p| p.join(name).join(&file_name))
+        .unwrap_or_else(|| PathBuf::from(file_name))
+}
+
+/// A single rename rule: files whose name matches `pattern` are renamed by
+/// expanding `template`.
+struct Rule {
+    pattern: Regex,
+    template: String,
+    /// Matches the names this rule *produces*, so retention can recognize the
+    /// files it renamed regardless of the template.
+    output: Regex,
+}
+
+impl Rule {
+    fn new(pattern: &str, template: String) -> anyhow::Result<Self> {
+        let pattern =
+            Regex::new(pattern).with_context(|| format!("invalid rule pattern \"{pattern}\""))?;
+        let output = output_pattern(&template)?;
+        Ok(Self {
+            pattern,
+            template,
+            output,
+        })
+    }
+}
+
+/// Build a regex matching any file name a template can produce by treating its
+/// literal text as fixed and every `${…}` token as a wildcard.
+fn output_pattern(template: &str) -> anyhow::Result<Regex> {
+    let mut pattern = String::from("^");
+    let mut last = 0;
+    for caps in RE_TEMPLATE_TOKEN.captures_iter(template) {
+        let whole = caps.get(0).unwrap();
+        pattern.push_str(&regex::escape(&template[last..whole.start()]));
+        pattern.push_str(".*");
+        last = whole.end();
+    }
+    pattern.push_str(&regex::escape(&template[last..]));
+    pattern.push('$');
+    Regex::new(&pattern).with_context(|| format!("invalid output pattern for \"{template}\""))
+}
+
+/// The set of rename rules, evaluated top-to-bottom with first match winning,
+/// plus the optional retention policy.
+struct Config {
+    rules: Vec<Rule>,
+    keep: Option<usize>,
+    retention_mode: RetentionMode,
+    upload: Option<UploadConfig>,
+}
+
+/// Where and how to upload renamed screenshots.
+struct UploadConfig {
+    endpoint: String,
+    auth_header: Option<String>,
+    response_field: String,
+}
+
+#[derive(Deserialize)]
+struct RawConfig {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RawRule>,
+    #[serde(default)]
+    keep: Option<usize>,
+    #[serde(default)]
+    retention_mode: Option<RetentionMode>,
+    #[serde(default)]
+    upload: Option<RawUpload>,
+}
+
+#[derive(Deserialize)]
+struct RawRule {
+    pattern: String,
+    template: String,
+}
+
+#[derive(Deserialize)]
+struct RawUpload {
+    endpoint: String,
+    #[serde(default)]
+    auth_header: Option<String>,
+    /// Field name or JSON pointer (leading `/`) for the URL in the response.
+    #[serde(default = "default_response_field")]
+    response_field: String,
+}
+
+fn default_response_field() -> String {
+    "url".to_string()
+}
+
+static RE_TEMPLATE_TOKEN: Lazy<Regex> = lazy_regex!(r"\$\{([^}]+)\}");
+
+impl Config {
+    /// Load the config from `path` if it exists, otherwise fall back to the
+    /// built-in rules so behavior is unchanged out of the box.
+    fn discover(path: &Path) -> anyhow::Result<Self> {
+        if path.exists() {
+            Self::load(path)
+        } else {
+            debug!(
+                "config file \"{}\" not found; using built-in rules",
+                path.display()
+            );
+            Ok(Self::builtin())
+        }
+    }
+
+    fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file \"{}\"", path.display()))?;
+        let raw: RawConfig = toml::from_str(&text)
+            .with_context(|| format!("failed to parse config file \"{}\"", path.display()))?;
+        let mut rules = Vec::with_capacity(raw.rules.len());
+        for raw_rule in raw.rules {
+            rules.push(Rule::new(&raw_rule.pattern, raw_rule.template)?);
+        }
+        Ok(Self {
+            rules,
+            keep: raw.keep,
+            retention_mode: raw.retention_mode.unwrap_or_default(),
+            upload: raw.upload.map(|u| UploadConfig {
+                endpoint: u.endpoint,
+                auth_header: u.auth_header,
+                response_field: u.response_field,
+            }),
+        })
+    }
+
+    /// The three rules the crate shipped before configs were supported.
+    fn builtin() -> Self {
+        let rules = vec![
+            Rule::new(
+                r"^スクリーンショット (\d{4}-\d{2}-\d{2} \d{6})\.png$",
+                "Screenshot ${1}.png".to_string(),
+            )
+            .unwrap(),
+            Rule::new(
+                r"^スクリーンショット_(\d{4})(\d{2})(\d{2})_(\d{6})\.png$",
+                "Screenshot ${1}-${2}-${3} ${4}.png".to_string(),
+            )
+            .unwrap(),
+            Rule::new(
+                r"^スクリーンショット(?: \(\d+\))?\.png$",
+                "Screenshot ${mtime:%Y-%m-%d %H%M%S}.png".to_string(),
+            )
+            .unwrap(),
+        ];
+        Self {
+            rules,
+            keep: None,
+            retention_mode: RetentionMode::default(),
+            upload: None,
+        }
+    }
+
+    /// Apply command-line overrides on top of the config-file values.
+    fn override_with(&mut self, args: &Args) {
+        if let Some(keep) = args.keep {
+            self.keep = Some(keep);
+        }
+        if let Some(mode) = args.retention_mode {
+            self.retention_mode = mode;
+        }
+        if !args.upload {
+            self.upload = None;
+        } else if self.upload.is_none() {
+            error!("--upload set but no [upload] section in config; uploads disabled");
+        }
+    }
+
+    /// Does `file_name` look like a screenshot this tool manages? Used to keep
+    /// retention from ever touching unrelated files in the directory.
+    fn is_known_screenshot(&self, file_name: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.output.is_match(file_name) || rule.pattern.is_match(file_name))
+    }
+
+    fn new_file_name(&self, path: &Path, file_name: &str) -> anyhow::Result<Option<String>> {
+        for rule in &self.rules {
+            if let Some(caps) = rule.pattern.captures(file_name) {
+                return Ok(Some(render_template(&rule.template, path, |i| {
+                    caps.get(i).map(|m| m.as_str())
+                })?));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Expand a rule template. `${1}`, `${2}`… are replaced with the corresponding
+/// capture group and `${mtime:<strftime>}` is replaced with the file's modified
+/// time formatted with the given chrono format string.
+fn render_template(
+    template: &str,
+    path: &Path,
+    group: impl Fn(usize) -> Option<&str>,
+) -> anyhow::Result<String> {
+    let mut out = String::new();
+    let mut last = 0;
+    for caps in RE_TEMPLATE_TOKEN.captures_iter(template) {
+        let whole = caps.get(0).unwrap();
+        out.push_str(&template[last..whole.start()]);
+
+        let token = caps.get(1).unwrap().as_str();
+        if let Some(fmt) = token.strip_prefix("mtime:") {
+            let metadata = path.metadata().context("failed to read file metadata")?;
+            let mtime = metadata.modified().context("failed to read mtime")?;
+            let dt: DateTime<Local> = mtime.into();
+            out.push_str(&dt.format(fmt).to_string());
+        } else if let Ok(index) = token.parse::<usize>() {
+            out.push_str(group(index).unwrap_or_default());
+        } else {
+            anyhow::bail!("unknown template token \"${{{token}}}\"");
+        }
+
+        last = whole.end();
+    }
+    out.push_str(&template[last..]);
+    Ok(out)
+}
 
 fn main() -> ExitCode {
     let args = Args::parse();
@@ -103,16 +358,42 @@ fn main() -> ExitCode {
 }
 
 fn run(args: &Args) -> anyhow::Result<()> {
-    scan_and_rename(&args.screenshots_dir, args.dry_run)?;
+    let mut config = Config::discover(&args.config)?;
+    config.override_with(args);
+    let config = Arc::new(config);
+
+    let last_url = Arc::new(Mutex::new(None::<String>));
+    let notify_enabled = Arc::new(AtomicBool::new(args.notify));
+
+    scan_and_rename(
+        &args.screenshots_dir,
+        args.dry_run,
+        &config,
+        &last_url,
+        &notify_enabled,
+    )?;
 
     if args.watch {
-        watch(&args.screenshots_dir, args.dry_run, &args.log_file)?;
+        watch(
+            &args.screenshots_dir,
+            args.dry_run,
+            &args.log_file,
+            &config,
+            &last_url,
+            &notify_enabled,
+        )?;
     }
 
     Ok(())
 }
 
-fn scan_and_rename(screenshot_dir: &Path, dry_run: bool) -> anyhow::Result<()> {
+fn scan_and_rename(
+    screenshot_dir: &Path,
+    dry_run: bool,
+    config: &Config,
+    last_url: &Mutex<Option<String>>,
+    notify_enabled: &AtomicBool,
+) -> anyhow::Result<()> {
     let screenshot_files =
         read_dir(screenshot_dir).context("failed to read screenshot directory")?;
 
@@ -122,22 +403,127 @@ fn scan_and_rename(screenshot_dir: &Path, dry_run: bool) -> anyhow::Result<()> {
             continue;
         };
 
-        process_entry(screenshot_dir, &entry, dry_run);
+        process_entry(
+            screenshot_dir,
+            &entry.path(),
+            dry_run,
+            config,
+            last_url,
+            notify_enabled,
+        );
     }
 
+    prune(screenshot_dir, dry_run, config);
+
     Ok(())
 }
 
-fn process_entry(screenshot_dir: &Path, entry: &DirEntry, dry_run: bool) {
-    let Ok(file_name) = entry.file_name().into_string() else {
-        error!(
-            "failed to convert file name to string: {:?}",
-            entry.file_name()
-        );
+/// Enforce the retention policy: keep the newest `config.keep` screenshots and
+/// prune (delete or archive) the rest. Never touches files that aren't
+/// recognized screenshots.
+fn prune(screenshot_dir: &Path, dry_run: bool, config: &Config) {
+    let Some(keep) = config.keep else {
         return;
     };
 
-    let Ok(new_file_name) = new_file_name(entry, &file_name) else {
+    let entries = match read_dir(screenshot_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("failed to read screenshot directory for retention: {e}");
+            return;
+        }
+    };
+
+    let mut screenshots = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(file_name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if !config.is_known_screenshot(&file_name) {
+            continue;
+        }
+        let Ok(mtime) = entry.metadata().and_then(|m| m.modified()) else {
+            continue;
+        };
+        screenshots.push((entry.path(), mtime));
+    }
+
+    for path in select_prunable(screenshots, keep) {
+        match config.retention_mode {
+            RetentionMode::Delete => {
+                if dry_run {
+                    info!("would prune \"{}\"", path.display());
+                    continue;
+                }
+                if let Err(e) = std::fs::remove_file(&path) {
+                    error!("failed to prune \"{}\": {e}", path.display());
+                    continue;
+                }
+                info!("pruned \"{}\"", path.display());
+            }
+            RetentionMode::Archive => {
+                let archive_dir = screenshot_dir.join("Archive");
+                let Some(file_name) = path.file_name() else {
+                    continue;
+                };
+                let dest = archive_dir.join(file_name);
+                if dry_run {
+                    info!(
+                        "would prune \"{}\" => \"{}\"",
+                        path.display(),
+                        dest.display()
+                    );
+                    continue;
+                }
+                if let Err(e) = std::fs::create_dir_all(&archive_dir) {
+                    error!(
+                        "failed to create archive directory \"{}\": {e}",
+                        archive_dir.display()
+                    );
+                    continue;
+                }
+                if let Err(e) = rename(&path, &dest) {
+                    error!(
+                        "failed to archive \"{}\" to \"{}\": {e}",
+                        path.display(),
+                        dest.display()
+                    );
+                    continue;
+                }
+                info!("archived \"{}\" => \"{}\"", path.display(), dest.display());
+            }
+        }
+    }
+}
+
+/// Newest first, keep the first `keep`, return the rest (oldest) for pruning.
+fn select_prunable(mut screenshots: Vec<(PathBuf, SystemTime)>, keep: usize) -> Vec<PathBuf> {
+    screenshots.sort_by(|a, b| b.1.cmp(&a.1));
+    screenshots
+        .into_iter()
+        .skip(keep)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+fn process_entry(
+    screenshot_dir: &Path,
+    old_path: &Path,
+    dry_run: bool,
+    config: &Config,
+    last_url: &Mutex<Option<String>>,
+    notify_enabled: &AtomicBool,
+) {
+    let Some(file_name) = old_path.file_name() else {
+        error!("failed to read file name for \"{}\"", old_path.display());
+        return;
+    };
+    let Some(file_name) = file_name.to_str() else {
+        error!("failed to convert file name to string: {file_name:?}");
+        return;
+    };
+
+    let Ok(new_file_name) = config.new_file_name(old_path, file_name) else {
         error!("failed to determine new file name for \"{}\"", file_name);
         return;
     };
@@ -147,8 +533,6 @@ fn process_entry(screenshot_dir: &Path, entry: &DirEntry, dry_run: bool) {
     };
     let new_path = screenshot_dir.join(&new_file_name);
 
-    let old_path = entry.path();
-
     if new_path.exists() {
         error!(
             "failed to rename \"{}\" to \"{}\": destination already exists",
@@ -167,7 +551,7 @@ fn process_entry(screenshot_dir: &Path, entry: &DirEntry, dry_run: bool) {
         return;
     }
 
-    if let Err(e) = rename(&old_path, &new_path) {
+    if let Err(e) = rename(old_path, &new_path) {
         error!(
             "failed to rename \"{}\" to \"{}\": {e}",
             old_path.display(),
@@ -176,27 +560,129 @@ fn process_entry(screenshot_dir: &Path, entry: &DirEntry, dry_run: bool) {
         return;
     }
     info!("\"{}\" => \"{}\"", old_path.display(), new_path.display());
+
+    if notify_enabled.load(Ordering::Relaxed) {
+        if let Err(e) = notify_rename(screenshot_dir, file_name, &new_file_name) {
+            error!("failed to show notification: {e}");
+        }
+    }
+
+    if let Some(upload) = &config.upload {
+        upload_and_copy(&new_path, upload, last_url);
+    }
+}
+
+/// Upload a freshly renamed screenshot, copy the returned URL to the clipboard,
+/// and remember it for the "Copy last URL" tray item.
+fn upload_and_copy(path: &Path, upload: &UploadConfig, last_url: &Mutex<Option<String>>) {
+    let url = match do_upload(path, upload) {
+        Ok(url) => url,
+        Err(e) => {
+            error!("failed to upload \"{}\": {e}", path.display());
+            return;
+        }
+    };
+
+    info!("uploaded \"{}\" => {}", path.display(), url);
+    if let Err(e) = set_clipboard(&url) {
+        error!("failed to copy URL to clipboard: {e}");
+    }
+    *last_url.lock().unwrap() = Some(url);
+}
+
+fn do_upload(path: &Path, upload: &UploadConfig) -> anyhow::Result<String> {
+    let form = reqwest::blocking::multipart::Form::new()
+        .file("file", path)
+        .context("failed to attach screenshot to upload")?;
+
+    let client = reqwest::blocking::Client::new();
+    let mut request = client.post(&upload.endpoint).multipart(form);
+    if let Some(header) = &upload.auth_header {
+        let (name, value) = header
+            .split_once(':')
+            .context("auth_header must be in \"Name: Value\" form")?;
+        request = request.header(name.trim(), value.trim());
+    }
+
+    let response = request
+        .send()
+        .context("upload request failed")?
+        .error_for_status()
+        .context("upload endpoint returned an error status")?;
+    let json: serde_json::Value = response
+        .json()
+        .context("failed to parse upload response as JSON")?;
+
+    let url = extract_url(&json, &upload.response_field).with_context(|| {
+        format!(
+            "no URL found at \"{}\" in upload response",
+            upload.response_field
+        )
+    })?;
+    Ok(url.to_string())
 }
 
-fn watch(screenshot_dir: &Path, dry_run: bool, log_file: &Path) -> anyhow::Result<()> {
+/// Pull the URL out of the JSON response. `response_field` is treated as a JSON
+/// pointer when it starts with `/`, otherwise as a top-level field name.
+fn extract_url<'a>(json: &'a serde_json::Value, response_field: &str) -> Option<&'a str> {
+    let value = if response_field.starts_with('/') {
+        json.pointer(response_field)
+    } else {
+        json.get(response_field)
+    };
+    value.and_then(|v| v.as_str())
+}
+
+fn set_clipboard(text: &str) -> anyhow::Result<()> {
+    let mut clipboard = arboard::Clipboard::new().context("failed to open clipboard")?;
+    clipboard
+        .set_text(text.to_string())
+        .context("failed to set clipboard text")?;
+    Ok(())
+}
+
+fn watch(
+    screenshot_dir: &Path,
+    dry_run: bool,
+    log_file: &Path,
+    config: &Arc<Config>,
+    last_url: &Arc<Mutex<Option<String>>>,
+    notify_enabled: &Arc<AtomicBool>,
+) -> anyhow::Result<()> {
     hide_console_window();
 
     let paused = Arc::new(AtomicBool::new(false));
 
     let dir = screenshot_dir.to_path_buf();
     let paused_clone = Arc::clone(&paused);
+    let config_clone = Arc::clone(config);
+    let last_url_clone = Arc::clone(last_url);
+    let notify_clone = Arc::clone(notify_enabled);
     std::thread::spawn(move || {
-        if let Err(e) = watch_and_rename(&dir, dry_run, &paused_clone) {
+        if let Err(e) = watch_and_rename(
+            &dir,
+            dry_run,
+            &paused_clone,
+            &config_clone,
+            &last_url_clone,
+            &notify_clone,
+        ) {
             error!("filesystem watcher error: {e}");
         }
     });
 
     let open_log_item = MenuItem::new("Open Log", true, None);
     let pause_item = MenuItem::new("Pause", true, None);
+    let copy_url_item = MenuItem::new("Copy last URL", true, None);
+    let notify_item = MenuItem::new(notify_menu_label(notify_enabled), true, None);
     let quit_item = MenuItem::new("Exit", true, None);
     let menu = Menu::new();
     menu.append(&pause_item)
         .context("failed to add menu item")?;
+    menu.append(&copy_url_item)
+        .context("failed to add menu item")?;
+    menu.append(&notify_item)
+        .context("failed to add menu item")?;
     menu.append(&open_log_item)
         .context("failed to add menu item")?;
     menu.append(&quit_item).context("failed to add menu item")?;
@@ -214,7 +700,24 @@ fn watch(screenshot_dir: &Path, dry_run: bool, log_file: &Path) -> anyhow::Resul
 
     info!("watching \"{}\" for changes...", screenshot_dir.display());
 
-    run_message_loop(&open_log_item, &pause_item, &quit_item, &paused, log_file);
+    if notify_enabled.load(Ordering::Relaxed)
+        && let Err(e) = notify_watching(screenshot_dir)
+    {
+        error!("failed to show notification: {e}");
+    }
+
+    run_message_loop(
+        &open_log_item,
+        &pause_item,
+        &copy_url_item,
+        &notify_item,
+        &quit_item,
+        &paused,
+        notify_enabled,
+        log_file,
+        last_url,
+        screenshot_dir,
+    );
 
     info!("exiting...");
     Ok(())
@@ -244,12 +747,18 @@ fn create_tray_icon_image() -> Icon {
     Icon::from_rgba(rgba, size, size).expect("failed to create tray icon")
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_message_loop(
     open_log_item: &MenuItem,
     pause_item: &MenuItem,
+    copy_url_item: &MenuItem,
+    notify_item: &MenuItem,
     quit_item: &MenuItem,
     paused: &AtomicBool,
+    notify_enabled: &AtomicBool,
     log_file: &Path,
+    last_url: &Mutex<Option<String>>,
+    screenshot_dir: &Path,
 ) {
     use windows::Win32::UI::WindowsAndMessaging::{
         DispatchMessageW, GetMessageW, MSG, PostQuitMessage, TranslateMessage,
@@ -257,6 +766,8 @@ fn run_message_loop(
 
     let open_log_id = open_log_item.id().clone();
     let pause_id = pause_item.id().clone();
+    let copy_url_id = copy_url_item.id().clone();
+    let notify_id = notify_item.id().clone();
     let quit_id = quit_item.id().clone();
     unsafe {
         let mut msg = MSG::default();
@@ -278,6 +789,29 @@ fn run_message_loop(
                         pause_item.set_text("Resume");
                         info!("watching paused");
                     }
+                } else if event.id == copy_url_id {
+                    let url = last_url.lock().unwrap().clone();
+                    match url {
+                        Some(url) => {
+                            if let Err(e) = set_clipboard(&url) {
+                                error!("failed to copy URL to clipboard: {e}");
+                            } else {
+                                info!("copied last URL to clipboard: {url}");
+                            }
+                        }
+                        None => info!("no URL to copy yet"),
+                    }
+                } else if event.id == notify_id {
+                    let was_enabled = notify_enabled.fetch_xor(true, Ordering::Relaxed);
+                    notify_item.set_text(notify_menu_label(notify_enabled));
+                    if was_enabled {
+                        info!("notifications disabled");
+                    } else {
+                        info!("notifications enabled");
+                        if let Err(e) = notify_watching(screenshot_dir) {
+                            error!("failed to show notification: {e}");
+                        }
+                    }
                 } else if event.id == quit_id {
                     info!("exit requested from tray menu");
                     PostQuitMessage(0);
@@ -291,56 +825,274 @@ fn watch_and_rename(
     screenshot_dir: &Path,
     dry_run: bool,
     paused: &AtomicBool,
+    config: &Config,
+    last_url: &Mutex<Option<String>>,
+    notify_enabled: &AtomicBool,
 ) -> anyhow::Result<()> {
     let (tx, rx) = mpsc::channel();
 
-    let mut watcher =
-        notify::recommended_watcher(tx).context("failed to create filesystem watcher")?;
-    watcher
+    // Coalesce the burst of events a single new screenshot produces into one
+    // notification so we rename it exactly once.
+    let mut debouncer = new_debouncer(Duration::from_millis(200), None, tx)
+        .context("failed to create filesystem watcher")?;
+    debouncer
+        .watcher()
         .watch(screenshot_dir, RecursiveMode::NonRecursive)
         .context("failed to watch screenshot directory")?;
 
+    // Canonicalize once so paths from the watcher backend (which may be
+    // absolute/verbatim even when `--screenshots-dir` is relative) compare
+    // equal to the directory we're watching.
+    let canonical_dir = screenshot_dir
+        .canonicalize()
+        .unwrap_or_else(|_| screenshot_dir.to_path_buf());
+
     for result in rx {
-        match result {
-            Ok(_) => {
-                std::thread::sleep(Duration::from_millis(200));
-                if paused.load(Ordering::Relaxed) {
-                    continue;
-                }
-                if let Err(e) = scan_and_rename(screenshot_dir, dry_run) {
-                    error!("failed to scan and rename: {e}");
+        let events = match result {
+            Ok(events) => events,
+            Err(errors) => {
+                for e in errors {
+                    error!("watch error: {e}");
                 }
+                continue;
+            }
+        };
+
+        if paused.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        // Process only the files that actually changed, skipping duplicate
+        // paths within the batch and anything that isn't a file sitting
+        // directly in the watched directory.
+        let mut seen = std::collections::HashSet::new();
+        let mut renamed_any = false;
+        for path in events.iter().flat_map(|event| event.paths.iter()) {
+            let Ok(path) = path.canonicalize() else {
+                continue;
+            };
+            if path.parent() != Some(canonical_dir.as_path()) || !path.is_file() {
+                continue;
             }
-            Err(e) => {
-                error!("watch error: {e}");
+            if !seen.insert(path.clone()) {
+                continue;
             }
+            process_entry(
+                &canonical_dir,
+                &path,
+                dry_run,
+                config,
+                last_url,
+                notify_enabled,
+            );
+            renamed_any = true;
+        }
+
+        if renamed_any {
+            prune(&canonical_dir, dry_run, config);
         }
     }
 
     Ok(())
 }
 
-fn new_file_name(entry: &DirEntry, file_name: &str) -> anyhow::Result<Option<String>> {
-    if let Some(caps) = RE_SNIPPING_TOOL_JA.captures(file_name) {
-        return Ok(Some(format!("Screenshot {}.png", &caps[1])));
+fn notify_menu_label(notify_enabled: &AtomicBool) -> &'static str {
+    if notify_enabled.load(Ordering::Relaxed) {
+        "Notifications: On"
+    } else {
+        "Notifications: Off"
     }
+}
+
+/// Toast shown when a screenshot is renamed. Clicking it opens the Screenshots
+/// folder in Explorer.
+fn notify_rename(screenshot_dir: &Path, old_name: &str, new_name: &str) -> anyhow::Result<()> {
+    show_toast(
+        "Screenshot renamed",
+        &format!("{old_name} => {new_name}"),
+        screenshot_dir,
+    )
+}
+
+/// Summary toast shown when watching starts.
+fn notify_watching(screenshot_dir: &Path) -> anyhow::Result<()> {
+    show_toast(
+        "Watching for screenshots",
+        &format!("watching {}", screenshot_dir.display()),
+        screenshot_dir,
+    )
+}
+
+/// Build and display a toast. `launch_dir` is wired up as a protocol-activated
+/// click action so the shell opens that folder in Explorer when the toast is
+/// clicked, without needing a registered COM activator.
+fn show_toast(title: &str, text: &str, launch_dir: &Path) -> anyhow::Result<()> {
+    use windows::Data::Xml::Dom::XmlDocument;
+    use windows::UI::Notifications::{ToastNotification, ToastNotificationManager};
+    use windows::core::HSTRING;
+
+    // Reuse the PowerShell AppUserModelID so toasts are shown without having to
+    // register our own shortcut in the Start menu.
+    const APP_ID: &str =
+        "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+    let launch = file_uri(launch_dir);
+    let xml = format!(
+        "<toast activationType=\"protocol\" launch=\"{}\">\
+            <visual><binding template=\"ToastGeneric\">\
+                <text>{}</text>\
+                <text>{}</text>\
+            </binding></visual>\
+        </toast>",
+        xml_escape(&launch),
+        xml_escape(title),
+        xml_escape(text),
+    );
+
+    let document = XmlDocument::new().context("failed to create toast document")?;
+    document
+        .LoadXml(&HSTRING::from(xml))
+        .context("failed to load toast XML")?;
+    let toast =
+        ToastNotification::CreateToastNotification(&document).context("failed to create toast")?;
+    let notifier = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))
+        .context("failed to create toast notifier")?;
+    notifier.Show(&toast).context("failed to show toast")?;
+    Ok(())
+}
 
-    if let Some(caps) = RE_OLD_SNIPPING_TOOL_JA.captures(file_name) {
-        return Ok(Some(format!(
-            "Screenshot {}-{}-{} {}.png",
-            &caps[1], &caps[2], &caps[3], &caps[4]
-        )));
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Turn a filesystem path into a `file:` URI the shell can launch: forward
+/// slashes for separators, `:` kept as is for the drive letter, and
+/// percent-encoding for everything else outside the unreserved set (so spaces
+/// and non-ASCII directory names survive).
+fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file:///");
+    for byte in path.display().to_string().replace('\\', "/").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char);
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
     }
+    uri
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if RE_SCREENSHOT_JA.is_match(file_name) {
-        let metadata = entry.metadata().context("failed to read file metadata")?;
-        let mtime = metadata.modified().context("failed to read mtime")?;
-        let dt: DateTime<Local> = mtime.into();
-        return Ok(Some(format!(
-            "Screenshot {}.png",
-            dt.format("%Y-%m-%d %H%M%S")
-        )));
+    #[test]
+    fn render_template_substitutes_capture_groups() {
+        let groups = ["2024", "06", "01", "120000"];
+        let rendered = render_template(
+            "Screenshot ${1}-${2}-${3} ${4}.png",
+            Path::new("unused"),
+            |i| groups.get(i - 1).copied(),
+        )
+        .unwrap();
+        assert_eq!(rendered, "Screenshot 2024-06-01 120000.png");
     }
 
-    Ok(None)
+    #[test]
+    fn render_template_missing_group_is_empty() {
+        let rendered = render_template("a${1}b", Path::new("unused"), |_| None).unwrap();
+        assert_eq!(rendered, "ab");
+    }
+
+    #[test]
+    fn render_template_rejects_unknown_token() {
+        let result = render_template("${nope}", Path::new("unused"), |_| None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn render_template_formats_mtime() {
+        let path = std::env::temp_dir().join("wsr_render_mtime_test.png");
+        std::fs::write(&path, b"test").unwrap();
+        let rendered =
+            render_template("Screenshot ${mtime:%Y-%m-%d %H%M%S}.png", &path, |_| None).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let re = Regex::new(r"^Screenshot \d{4}-\d{2}-\d{2} \d{6}\.png$").unwrap();
+        assert!(re.is_match(&rendered), "unexpected render: {rendered}");
+    }
+
+    #[test]
+    fn output_pattern_matches_rendered_names() {
+        let output = output_pattern("${1}_${mtime:%H%M%S}.png").unwrap();
+        assert!(output.is_match("2024-06-01_120000.png"));
+        assert!(!output.is_match("holiday.jpg"));
+    }
+
+    #[test]
+    fn is_known_screenshot_recognizes_custom_outputs() {
+        // A config whose output format differs from the built-in "Screenshot …".
+        let config = Config {
+            rules: vec![Rule::new(r"^Capture_(\d+)\.png$", "${1}_shot.png".to_string()).unwrap()],
+            keep: None,
+            retention_mode: RetentionMode::default(),
+            upload: None,
+        };
+        assert!(config.is_known_screenshot("42_shot.png"));
+        assert!(config.is_known_screenshot("Capture_42.png"));
+        assert!(!config.is_known_screenshot("notes.txt"));
+    }
+
+    #[test]
+    fn select_prunable_keeps_newest() {
+        let base = SystemTime::UNIX_EPOCH;
+        let screenshots = vec![
+            (PathBuf::from("old.png"), base + Duration::from_secs(10)),
+            (PathBuf::from("newest.png"), base + Duration::from_secs(30)),
+            (PathBuf::from("middle.png"), base + Duration::from_secs(20)),
+        ];
+        let pruned = select_prunable(screenshots, 1);
+        assert_eq!(
+            pruned,
+            vec![PathBuf::from("middle.png"), PathBuf::from("old.png")]
+        );
+    }
+
+    #[test]
+    fn extract_url_reads_field_name() {
+        let json = serde_json::json!({ "url": "https://example.com/a.png" });
+        assert_eq!(extract_url(&json, "url"), Some("https://example.com/a.png"));
+    }
+
+    #[test]
+    fn extract_url_reads_json_pointer() {
+        let json = serde_json::json!({ "data": { "link": "https://example.com/b.png" } });
+        assert_eq!(
+            extract_url(&json, "/data/link"),
+            Some("https://example.com/b.png")
+        );
+    }
+
+    #[test]
+    fn extract_url_missing_is_none() {
+        let json = serde_json::json!({ "other": 1 });
+        assert_eq!(extract_url(&json, "url"), None);
+    }
+
+    #[test]
+    fn xml_escape_escapes_markup() {
+        assert_eq!(
+            xml_escape("a & b <c> \"d\""),
+            "a &amp; b &lt;c&gt; &quot;d&quot;"
+        );
+    }
+
+    #[test]
+    fn file_uri_encodes_separators_and_spaces() {
+        let uri = file_uri(Path::new(r"C:\Users\me\My Pictures\Screenshots"));
+        assert_eq!(uri, "file:///C:/Users/me/My%20Pictures/Screenshots");
+    }
 }